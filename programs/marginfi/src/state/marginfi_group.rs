@@ -1,7 +1,8 @@
-use crate::{check, math_error, set_if_some, MarginfiResult};
+use crate::{check, constants::SECONDS_PER_YEAR, math_error, set_if_some, MarginfiResult};
 use anchor_lang::prelude::*;
 use fixed::types::I80F48;
 use fixed_macro::types::I80F48;
+use pyth_sdk_solana::state::load_price_account;
 
 use super::marginfi_account::WeightType;
 
@@ -98,6 +99,14 @@ pub struct Bank {
 
     pub total_borrow_shares: I80F48,
     pub total_deposit_shares: I80F48,
+
+    /// Deposit shares owed to the fee vault, accrued from the protocol's cut of borrow interest.
+    pub fee_vault_deposit_shares: I80F48,
+    /// Deposit shares owed to the insurance vault, accrued from the insurance fund's cut of borrow interest.
+    pub insurance_vault_deposit_shares: I80F48,
+
+    /// Unix timestamp of the last time interest was accrued on this bank.
+    pub last_update: i64,
 }
 
 impl Bank {
@@ -118,7 +127,118 @@ impl Bank {
             config,
             total_borrow_shares: I80F48::ZERO,
             total_deposit_shares: I80F48::ZERO,
+            fee_vault_deposit_shares: I80F48::ZERO,
+            insurance_vault_deposit_shares: I80F48::ZERO,
+            last_update: 0,
+        }
+    }
+
+    /// Accrues interest on the bank's deposits and liabilities since `last_update`, compounding
+    /// the share values by the borrowing/lending rates implied by the current utilization ratio.
+    /// The protocol and insurance fund's cut of the accrued borrow interest is minted as new
+    /// deposit shares credited to the fee and insurance vaults.
+    ///
+    /// No-ops if `current_timestamp <= last_update` (e.g. clock skew).
+    pub fn accrue_interest(&mut self, current_timestamp: i64) -> MarginfiResult {
+        let time_delta = current_timestamp - self.last_update;
+        if time_delta <= 0 {
+            return Ok(());
+        }
+        let time_delta = I80F48::from_num(time_delta);
+
+        let total_deposits = self.get_deposit_value(self.total_deposit_shares)?;
+        let total_liabilities = self.get_liability_value(self.total_borrow_shares)?;
+
+        let utilization_ratio = if total_deposits > I80F48::ZERO {
+            total_liabilities
+                .checked_div(total_deposits)
+                .ok_or_else(math_error!())?
+        } else {
+            I80F48::ZERO
+        };
+
+        let (lending_rate, borrowing_rate, group_fee_rate, insurance_fee_rate) = self
+            .config
+            .interest_rate_config
+            .calc_interest_rate(utilization_ratio)?;
+
+        let liability_interest_factor = I80F48::ONE
+            .checked_add(
+                borrowing_rate
+                    .checked_mul(time_delta)
+                    .ok_or_else(math_error!())?
+                    .checked_div(SECONDS_PER_YEAR)
+                    .ok_or_else(math_error!())?,
+            )
+            .ok_or_else(math_error!())?;
+
+        let deposit_interest_factor = I80F48::ONE
+            .checked_add(
+                lending_rate
+                    .checked_mul(time_delta)
+                    .ok_or_else(math_error!())?
+                    .checked_div(SECONDS_PER_YEAR)
+                    .ok_or_else(math_error!())?,
+            )
+            .ok_or_else(math_error!())?;
+
+        self.liability_share_value = self
+            .liability_share_value
+            .checked_mul(liability_interest_factor)
+            .ok_or_else(math_error!())?;
+        self.deposit_share_value = self
+            .deposit_share_value
+            .checked_mul(deposit_interest_factor)
+            .ok_or_else(math_error!())?;
+
+        let borrow_interest_value = total_liabilities
+            .checked_mul(liability_interest_factor - I80F48::ONE)
+            .ok_or_else(math_error!())?;
+        let lending_interest_value = total_deposits
+            .checked_mul(deposit_interest_factor - I80F48::ONE)
+            .ok_or_else(math_error!())?;
+
+        // The spread between what borrowers pay and what depositors earn is split between
+        // the group fee vault and the insurance vault, in proportion to their fixed APRs.
+        let total_fee_rate = group_fee_rate
+            .checked_add(insurance_fee_rate)
+            .ok_or_else(math_error!())?;
+        let total_fee_value = borrow_interest_value
+            .checked_sub(lending_interest_value)
+            .ok_or_else(math_error!())?;
+
+        if total_fee_value > I80F48::ZERO && total_fee_rate > I80F48::ZERO {
+            let group_fee_value = total_fee_value
+                .checked_mul(group_fee_rate)
+                .ok_or_else(math_error!())?
+                .checked_div(total_fee_rate)
+                .ok_or_else(math_error!())?;
+            let insurance_fee_value = total_fee_value
+                .checked_sub(group_fee_value)
+                .ok_or_else(math_error!())?;
+
+            let group_fee_shares = self.get_deposit_shares(group_fee_value)?;
+            let insurance_fee_shares = self.get_deposit_shares(insurance_fee_value)?;
+
+            self.fee_vault_deposit_shares = self
+                .fee_vault_deposit_shares
+                .checked_add(group_fee_shares)
+                .ok_or_else(math_error!())?;
+            self.insurance_vault_deposit_shares = self
+                .insurance_vault_deposit_shares
+                .checked_add(insurance_fee_shares)
+                .ok_or_else(math_error!())?;
+            self.total_deposit_shares = self
+                .total_deposit_shares
+                .checked_add(group_fee_shares)
+                .ok_or_else(math_error!())?
+                .checked_add(insurance_fee_shares)
+                .ok_or_else(math_error!())?;
         }
+
+        self.last_update = current_timestamp;
+
+        Ok(())
     }
 
     pub fn get_liability_value(&self, shares: I80F48) -> MarginfiResult<I80F48> {
@@ -169,6 +289,107 @@ impl Bank {
             .total_borrow_shares
             .checked_add(shares)
             .ok_or_else(math_error!())?;
+
+        if shares.is_positive() {
+            let total_liability_value = self.get_liability_value(self.total_borrow_shares)?;
+
+            check!(
+                total_liability_value < I80F48::from_num(self.config.borrow_limit),
+                crate::prelude::MarginfiError::BankLiabilityCapacityExceeded
+            )
+        }
+
+        Ok(())
+    }
+
+    /// Caps how much of a liquidatee's liability value for this bank may be repaid in a single
+    /// liquidation call, unless it's already below `liquidation_close_amount`.
+    pub fn calc_max_liquidatable_liability_value(
+        &self,
+        liability_value: I80F48,
+    ) -> MarginfiResult<I80F48> {
+        let dust_threshold: I80F48 = self.config.liquidation_close_amount.into();
+
+        if liability_value < dust_threshold {
+            return Ok(liability_value);
+        }
+
+        let close_factor: I80F48 = self.config.liquidation_close_factor.into();
+
+        Ok(liability_value
+            .checked_mul(close_factor)
+            .ok_or_else(math_error!())?
+            .min(liability_value))
+    }
+
+    /// Converts a repaid liability value into the deposit shares a liquidator seizes as
+    /// collateral, applying the configured liquidation bonus.
+    pub fn calc_liquidation_seized_deposit_shares(
+        &self,
+        repaid_liability_value: I80F48,
+    ) -> MarginfiResult<I80F48> {
+        let bonus: I80F48 = self.config.liquidation_bonus.into();
+
+        let seized_value = repaid_liability_value
+            .checked_mul(I80F48::ONE.checked_add(bonus).ok_or_else(math_error!())?)
+            .ok_or_else(math_error!())?;
+
+        self.get_deposit_shares(seized_value)
+    }
+
+    /// Validates `liquidation_close_factor` and `liquidation_bonus` so a bank can't end up with
+    /// liquidation silently disabled (a `0` close factor repays nothing) or a nonsensical bonus.
+    fn validate_liquidation_params(&self) -> MarginfiResult {
+        let close_factor: I80F48 = self.config.liquidation_close_factor.into();
+        let bonus: I80F48 = self.config.liquidation_bonus.into();
+
+        check!(
+            close_factor > I80F48::ZERO && close_factor <= I80F48::ONE,
+            crate::prelude::MarginfiError::InvalidConfig
+        );
+        check!(
+            bonus >= I80F48::ZERO,
+            crate::prelude::MarginfiError::InvalidConfig
+        );
+
+        Ok(())
+    }
+
+    /// Validates `oracle_config` so a bank can't end up with an oracle that rejects every real
+    /// Pyth reading (a `0` confidence filter or `0` staleness window are both unusable defaults).
+    fn validate_oracle_config(&self) -> MarginfiResult {
+        let conf_filter: I80F48 = self.config.oracle_config.conf_filter.into();
+
+        check!(
+            conf_filter > I80F48::ZERO,
+            crate::prelude::MarginfiError::InvalidConfig
+        );
+        check!(
+            self.config.oracle_config.max_staleness_slots > 0,
+            crate::prelude::MarginfiError::InvalidConfig
+        );
+
+        Ok(())
+    }
+
+    /// Validates `stable_price_model` so a bank can't end up with a `0` `delay_interval_seconds`,
+    /// which divides by zero on every `update()` call after the first.
+    fn validate_stable_price_model(&self) -> MarginfiResult {
+        let max_move_fraction: I80F48 = self
+            .config
+            .stable_price_model
+            .max_relative_move_per_interval
+            .into();
+
+        check!(
+            self.config.stable_price_model.delay_interval_seconds > 0,
+            crate::prelude::MarginfiError::InvalidConfig
+        );
+        check!(
+            max_move_fraction >= I80F48::ZERO && max_move_fraction <= I80F48::ONE,
+            crate::prelude::MarginfiError::InvalidConfig
+        );
+
         Ok(())
     }
 
@@ -187,11 +408,126 @@ impl Bank {
             config.liability_weight_maint
         );
         set_if_some!(self.config.max_capacity, config.max_capacity);
+        set_if_some!(self.config.borrow_limit, config.borrow_limit);
         set_if_some!(self.config.pyth_oracle, config.pyth_oracle);
+        set_if_some!(
+            self.config.interest_rate_config,
+            config.interest_rate_config
+        );
+        set_if_some!(self.config.stable_price_model, config.stable_price_model);
+        set_if_some!(
+            self.config.liquidation_close_factor,
+            config.liquidation_close_factor
+        );
+        set_if_some!(self.config.liquidation_bonus, config.liquidation_bonus);
+        set_if_some!(
+            self.config.liquidation_close_amount,
+            config.liquidation_close_amount
+        );
+        set_if_some!(
+            self.config.maint_weight_target,
+            config.maint_weight_target
+        );
+        set_if_some!(self.config.maint_weight_start, config.maint_weight_start);
+        set_if_some!(self.config.ramp_start_ts, config.ramp_start_ts);
+        set_if_some!(self.config.ramp_end_ts, config.ramp_end_ts);
+        set_if_some!(self.config.oracle_config, config.oracle_config);
+
+        self.config.interest_rate_config.validate()?;
+        self.validate_liquidation_params()?;
+        self.validate_oracle_config()?;
+        self.validate_stable_price_model()?;
+
+        Ok(())
+    }
+}
+
+#[cfg_attr(
+    any(feature = "test", feature = "client"),
+    derive(Debug, PartialEq, Eq)
+)]
+#[zero_copy]
+#[derive(Default, AnchorDeserialize, AnchorSerialize)]
+/// A lagging price that limits how fast collateral/liability valuations can move.
+pub struct StablePriceModel {
+    pub stable_price: WrappedI80F48,
+    pub last_update_timestamp: i64,
+
+    /// How often the stable price is allowed to move by up to `max_relative_move_per_interval`.
+    pub delay_interval_seconds: i64,
+    /// Maximum fraction of `stable_price` that it may move by, per `delay_interval_seconds`.
+    pub max_relative_move_per_interval: WrappedI80F48,
+}
+
+impl StablePriceModel {
+    /// Moves `stable_price` towards `oracle_price`, clamped per elapsed delay interval.
+    pub fn update(&mut self, oracle_price: I80F48, current_timestamp: i64) -> MarginfiResult {
+        let stable_price: I80F48 = self.stable_price.into();
+
+        // Not yet bootstrapped: seed the stable price from the live oracle instead of trying to
+        // move towards it by a fraction of itself, which can never leave zero.
+        if stable_price == I80F48::ZERO {
+            self.stable_price = oracle_price.into();
+            self.last_update_timestamp = current_timestamp;
+            return Ok(());
+        }
+
+        let elapsed = current_timestamp - self.last_update_timestamp;
+        if elapsed <= 0 {
+            return Ok(());
+        }
+        let max_move_fraction: I80F48 = self.max_relative_move_per_interval.into();
+
+        let intervals_elapsed = I80F48::from_num(elapsed)
+            .checked_div(I80F48::from_num(self.delay_interval_seconds))
+            .ok_or_else(math_error!())?;
+
+        let max_move = stable_price
+            .checked_mul(max_move_fraction)
+            .ok_or_else(math_error!())?
+            .checked_mul(intervals_elapsed)
+            .ok_or_else(math_error!())?
+            .abs();
+
+        let delta = oracle_price
+            .checked_sub(stable_price)
+            .ok_or_else(math_error!())?;
+
+        let clamped_delta = if delta.abs() > max_move {
+            if delta.is_positive() {
+                max_move
+            } else {
+                -max_move
+            }
+        } else {
+            delta
+        };
+
+        self.stable_price = stable_price
+            .checked_add(clamped_delta)
+            .ok_or_else(math_error!())?
+            .into();
+        self.last_update_timestamp = current_timestamp;
+
         Ok(())
     }
 }
 
+#[cfg_attr(
+    any(feature = "test", feature = "client"),
+    derive(Debug, PartialEq, Eq)
+)]
+#[zero_copy]
+#[derive(Default, AnchorDeserialize, AnchorSerialize)]
+/// Bounds on how stale or uncertain a Pyth price reading is allowed to be before it is rejected.
+pub struct OracleConfig {
+    /// Maximum confidence interval allowed, expressed as a fraction of the price
+    /// (e.g. 0.1 rejects a price whose `conf` exceeds 10% of `price`).
+    pub conf_filter: WrappedI80F48,
+    /// Maximum number of slots a price is allowed to lag behind `current_slot`.
+    pub max_staleness_slots: u64,
+}
+
 #[cfg_attr(
     any(feature = "test", feature = "client"),
     derive(Debug, PartialEq, Eq)
@@ -207,22 +543,294 @@ pub struct BankConfig {
     pub liability_weight_maint: WrappedI80F48,
 
     pub max_capacity: u64,
+    pub borrow_limit: u64,
 
     pub pyth_oracle: Pubkey,
+
+    pub interest_rate_config: InterestRateConfig,
+
+    /// `liability_weight_maint` the bank is ramping towards, over `[ramp_start_ts, ramp_end_ts]`.
+    pub maint_weight_target: WrappedI80F48,
+    /// `liability_weight_maint` at the start of the ramp.
+    pub maint_weight_start: WrappedI80F48,
+    pub ramp_start_ts: i64,
+    pub ramp_end_ts: i64,
+
+    pub stable_price_model: StablePriceModel,
+
+    /// Maximum fraction of a liquidatee's liability shares for this bank that may be repaid in
+    /// a single liquidation call.
+    pub liquidation_close_factor: WrappedI80F48,
+    /// Bonus applied to the repaid value to determine how much collateral the liquidator seizes,
+    /// e.g. 0.05 for a 5% bonus.
+    pub liquidation_bonus: WrappedI80F48,
+    /// Liability value below which a liquidation may close out the position entirely, bypassing
+    /// `liquidation_close_factor`, so dust positions don't become uncollectable.
+    pub liquidation_close_amount: WrappedI80F48,
+
+    pub oracle_config: OracleConfig,
 }
 
 impl BankConfig {
-    pub fn get_weights(&self, weight_type: WeightType) -> (I80F48, I80F48) {
-        match weight_type {
+    /// Loads the Pyth price at `oracle_ai`, rejecting it if it is too stale (more than
+    /// `oracle_config.max_staleness_slots` behind `current_slot`) or too uncertain (confidence
+    /// interval wider than `oracle_config.conf_filter * price`).
+    pub fn get_price(&self, oracle_ai: &AccountInfo, current_slot: u64) -> MarginfiResult<I80F48> {
+        check!(
+            oracle_ai.key == &self.pyth_oracle,
+            crate::prelude::MarginfiError::InvalidOracleAccount
+        );
+
+        let data = oracle_ai
+            .try_borrow_data()
+            .map_err(|_| crate::prelude::MarginfiError::InvalidOracleAccount)?;
+        let price_account = load_price_account(&data)
+            .map_err(|_| crate::prelude::MarginfiError::InvalidOracleAccount)?;
+        let price_feed = price_account.to_price_feed(oracle_ai.key);
+        // `get_current_price` returns `None` unless the feed's aggregate status is `Trading`,
+        // so a halted/unknown-status feed is rejected here rather than trusted at face value.
+        let price_data = price_feed
+            .get_current_price()
+            .ok_or(crate::prelude::MarginfiError::UnreliableOracle)?;
+
+        self.validate_and_scale_price(
+            price_data.price,
+            price_data.conf,
+            price_data.expo,
+            price_account.agg.pub_slot,
+            current_slot,
+        )
+    }
+
+    /// Rejects a raw Pyth reading that is too stale or too uncertain, and scales it from Pyth's
+    /// `price * 10^expo` representation into a plain `I80F48`. Split out from `get_price` so the
+    /// staleness/confidence math can be exercised without a real Pyth account.
+    fn validate_and_scale_price(
+        &self,
+        raw_price: i64,
+        raw_conf: u64,
+        expo: i32,
+        pub_slot: u64,
+        current_slot: u64,
+    ) -> MarginfiResult<I80F48> {
+        check!(
+            pub_slot
+                .checked_add(self.oracle_config.max_staleness_slots)
+                .ok_or_else(math_error!())?
+                >= current_slot,
+            crate::prelude::MarginfiError::StaleOracle
+        );
+
+        let expo_scale =
+            I80F48::checked_from_num(10u64.pow(expo.unsigned_abs())).ok_or_else(math_error!())?;
+
+        let (price, conf) = if expo < 0 {
+            (
+                I80F48::from_num(raw_price)
+                    .checked_div(expo_scale)
+                    .ok_or_else(math_error!())?,
+                I80F48::from_num(raw_conf)
+                    .checked_div(expo_scale)
+                    .ok_or_else(math_error!())?,
+            )
+        } else {
+            (
+                I80F48::from_num(raw_price)
+                    .checked_mul(expo_scale)
+                    .ok_or_else(math_error!())?,
+                I80F48::from_num(raw_conf)
+                    .checked_mul(expo_scale)
+                    .ok_or_else(math_error!())?,
+            )
+        };
+
+        let conf_filter: I80F48 = self.oracle_config.conf_filter.into();
+        let max_conf = price.checked_mul(conf_filter).ok_or_else(math_error!())?;
+
+        check!(
+            conf <= max_conf,
+            crate::prelude::MarginfiError::UnreliableOracle
+        );
+
+        Ok(price)
+    }
+    /// Returns the (deposit, liability) weights in effect at `current_timestamp`.
+    pub fn get_weights(
+        &self,
+        weight_type: WeightType,
+        current_timestamp: i64,
+    ) -> MarginfiResult<(I80F48, I80F48)> {
+        Ok(match weight_type {
             WeightType::Initial => (
                 self.deposit_weight_init.into(),
                 self.liability_weight_init.into(),
             ),
             WeightType::Maintenance => (
                 self.deposit_weight_maint.into(),
-                self.liability_weight_maint.into(),
+                self.current_liability_weight_maint(current_timestamp)?,
             ),
+        })
+    }
+
+    fn current_liability_weight_maint(&self, current_timestamp: i64) -> MarginfiResult<I80F48> {
+        // No ramp configured: use the static weight.
+        if self.ramp_end_ts <= self.ramp_start_ts {
+            return Ok(self.liability_weight_maint.into());
         }
+
+        let start_weight: I80F48 = self.maint_weight_start.into();
+        let target_weight: I80F48 = self.maint_weight_target.into();
+
+        if current_timestamp <= self.ramp_start_ts {
+            return Ok(start_weight);
+        }
+        if current_timestamp >= self.ramp_end_ts {
+            return Ok(target_weight);
+        }
+
+        let progress = I80F48::from_num(current_timestamp - self.ramp_start_ts)
+            .checked_div(I80F48::from_num(self.ramp_end_ts - self.ramp_start_ts))
+            .ok_or_else(math_error!())?;
+
+        Ok(start_weight
+            .checked_add(
+                target_weight
+                    .checked_sub(start_weight)
+                    .ok_or_else(math_error!())?
+                    .checked_mul(progress)
+                    .ok_or_else(math_error!())?,
+            )
+            .ok_or_else(math_error!())?)
+    }
+
+    /// Returns the price to use when valuing an asset (deposit).
+    pub fn get_asset_price(&self, oracle_price: I80F48) -> I80F48 {
+        oracle_price.min(self.stable_price_model.stable_price.into())
+    }
+
+    /// Returns the price to use when valuing a liability.
+    pub fn get_liability_price(&self, oracle_price: I80F48) -> I80F48 {
+        oracle_price.max(self.stable_price_model.stable_price.into())
+    }
+}
+
+#[cfg_attr(
+    any(feature = "test", feature = "client"),
+    derive(Debug, PartialEq, Eq)
+)]
+#[zero_copy]
+#[derive(Default, AnchorDeserialize, AnchorSerialize)]
+/// Piecewise-linear utilization-based interest rate curve.
+pub struct InterestRateConfig {
+    pub optimal_utilization_rate: WrappedI80F48,
+    pub min_borrow_rate: WrappedI80F48,
+    pub optimal_borrow_rate: WrappedI80F48,
+    pub max_borrow_rate: WrappedI80F48,
+
+    pub insurance_fee_fixed_apr: WrappedI80F48,
+    pub protocol_fixed_fee_apr: WrappedI80F48,
+}
+
+impl InterestRateConfig {
+    /// Returns (lending_rate, borrowing_rate, group_fee_apr, insurance_fee_apr) for the given
+    /// utilization ratio.
+    pub fn calc_interest_rate(
+        &self,
+        utilization_ratio: I80F48,
+    ) -> MarginfiResult<(I80F48, I80F48, I80F48, I80F48)> {
+        let optimal_ur: I80F48 = self.optimal_utilization_rate.into();
+        let min_rate: I80F48 = self.min_borrow_rate.into();
+        let optimal_rate: I80F48 = self.optimal_borrow_rate.into();
+        let max_rate: I80F48 = self.max_borrow_rate.into();
+
+        let base_rate = if utilization_ratio <= optimal_ur {
+            let slope = optimal_rate
+                .checked_sub(min_rate)
+                .ok_or_else(math_error!())?;
+
+            min_rate
+                .checked_add(
+                    utilization_ratio
+                        .checked_div(optimal_ur)
+                        .ok_or_else(math_error!())?
+                        .checked_mul(slope)
+                        .ok_or_else(math_error!())?,
+                )
+                .ok_or_else(math_error!())?
+        } else {
+            let slope = max_rate
+                .checked_sub(optimal_rate)
+                .ok_or_else(math_error!())?;
+            let excess_ur = utilization_ratio
+                .checked_sub(optimal_ur)
+                .ok_or_else(math_error!())?;
+            let excess_range = I80F48::ONE
+                .checked_sub(optimal_ur)
+                .ok_or_else(math_error!())?;
+
+            optimal_rate
+                .checked_add(
+                    excess_ur
+                        .checked_div(excess_range)
+                        .ok_or_else(math_error!())?
+                        .checked_mul(slope)
+                        .ok_or_else(math_error!())?,
+                )
+                .ok_or_else(math_error!())?
+        };
+
+        let insurance_fee_apr: I80F48 = self.insurance_fee_fixed_apr.into();
+        let group_fee_apr: I80F48 = self.protocol_fixed_fee_apr.into();
+        let total_fee_apr = group_fee_apr
+            .checked_add(insurance_fee_apr)
+            .ok_or_else(math_error!())?;
+
+        let borrowing_rate = base_rate;
+        let lending_rate = base_rate
+            .checked_mul(utilization_ratio)
+            .ok_or_else(math_error!())?
+            .checked_mul(
+                I80F48::ONE
+                    .checked_sub(total_fee_apr)
+                    .ok_or_else(math_error!())?,
+            )
+            .ok_or_else(math_error!())?;
+
+        Ok((lending_rate, borrowing_rate, group_fee_apr, insurance_fee_apr))
+    }
+
+    /// Validates `min <= optimal <= max` and that rates/fees are non-negative and sane.
+    pub fn validate(&self) -> MarginfiResult {
+        let optimal_ur: I80F48 = self.optimal_utilization_rate.into();
+        let min_rate: I80F48 = self.min_borrow_rate.into();
+        let optimal_rate: I80F48 = self.optimal_borrow_rate.into();
+        let max_rate: I80F48 = self.max_borrow_rate.into();
+
+        check!(
+            optimal_ur > I80F48::ZERO && optimal_ur < I80F48::ONE,
+            crate::prelude::MarginfiError::InvalidConfig
+        );
+        check!(
+            min_rate >= I80F48::ZERO && min_rate <= optimal_rate && optimal_rate <= max_rate,
+            crate::prelude::MarginfiError::InvalidConfig
+        );
+
+        let insurance_fee_apr: I80F48 = self.insurance_fee_fixed_apr.into();
+        let group_fee_apr: I80F48 = self.protocol_fixed_fee_apr.into();
+
+        check!(
+            insurance_fee_apr >= I80F48::ZERO && group_fee_apr >= I80F48::ZERO,
+            crate::prelude::MarginfiError::InvalidConfig
+        );
+        check!(
+            group_fee_apr
+                .checked_add(insurance_fee_apr)
+                .ok_or_else(math_error!())?
+                <= I80F48::ONE,
+            crate::prelude::MarginfiError::InvalidConfig
+        );
+
+        Ok(())
     }
 }
 
@@ -254,6 +862,369 @@ pub struct BankConfigOpt {
     pub liability_weight_maint: Option<WrappedI80F48>,
 
     pub max_capacity: Option<u64>,
+    pub borrow_limit: Option<u64>,
 
     pub pyth_oracle: Option<Pubkey>,
+
+    pub interest_rate_config: Option<InterestRateConfig>,
+
+    pub stable_price_model: Option<StablePriceModel>,
+
+    pub liquidation_close_factor: Option<WrappedI80F48>,
+    pub liquidation_bonus: Option<WrappedI80F48>,
+    pub liquidation_close_amount: Option<WrappedI80F48>,
+
+    pub maint_weight_target: Option<WrappedI80F48>,
+    pub maint_weight_start: Option<WrappedI80F48>,
+    pub ramp_start_ts: Option<i64>,
+    pub ramp_end_ts: Option<i64>,
+
+    pub oracle_config: Option<OracleConfig>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ir_config(
+        optimal_utilization_rate: f64,
+        min_borrow_rate: f64,
+        optimal_borrow_rate: f64,
+        max_borrow_rate: f64,
+        insurance_fee_fixed_apr: f64,
+        protocol_fixed_fee_apr: f64,
+    ) -> InterestRateConfig {
+        InterestRateConfig {
+            optimal_utilization_rate: I80F48::from_num(optimal_utilization_rate).into(),
+            min_borrow_rate: I80F48::from_num(min_borrow_rate).into(),
+            optimal_borrow_rate: I80F48::from_num(optimal_borrow_rate).into(),
+            max_borrow_rate: I80F48::from_num(max_borrow_rate).into(),
+            insurance_fee_fixed_apr: I80F48::from_num(insurance_fee_fixed_apr).into(),
+            protocol_fixed_fee_apr: I80F48::from_num(protocol_fixed_fee_apr).into(),
+        }
+    }
+
+    #[test]
+    fn calc_interest_rate_at_zero_utilization_is_min_rate() {
+        let config = ir_config(0.8, 0.01, 0.1, 1.0, 0.0, 0.0);
+
+        let (lending_rate, borrowing_rate, _, _) =
+            config.calc_interest_rate(I80F48::ZERO).unwrap();
+
+        assert_eq!(borrowing_rate, I80F48::from_num(0.01));
+        assert_eq!(lending_rate, I80F48::ZERO);
+    }
+
+    #[test]
+    fn calc_interest_rate_at_optimal_utilization_is_optimal_rate() {
+        let config = ir_config(0.8, 0.01, 0.1, 1.0, 0.0, 0.0);
+
+        let (_, borrowing_rate, _, _) = config
+            .calc_interest_rate(I80F48::from_num(0.8))
+            .unwrap();
+
+        assert_eq!(borrowing_rate, I80F48::from_num(0.1));
+    }
+
+    #[test]
+    fn calc_interest_rate_above_optimal_follows_second_segment() {
+        let config = ir_config(0.8, 0.01, 0.1, 1.0, 0.0, 0.0);
+
+        // Halfway between optimal (0.8) and full utilization (1.0).
+        let (_, borrowing_rate, _, _) = config
+            .calc_interest_rate(I80F48::from_num(0.9))
+            .unwrap();
+
+        assert_eq!(borrowing_rate, I80F48::from_num(0.55));
+    }
+
+    #[test]
+    fn calc_interest_rate_splits_fees_between_group_and_insurance() {
+        let config = ir_config(0.8, 0.0, 0.1, 1.0, 0.02, 0.03);
+
+        let (lending_rate, borrowing_rate, group_fee_apr, insurance_fee_apr) = config
+            .calc_interest_rate(I80F48::from_num(0.8))
+            .unwrap();
+
+        assert_eq!(borrowing_rate, I80F48::from_num(0.1));
+        assert_eq!(group_fee_apr, I80F48::from_num(0.03));
+        assert_eq!(insurance_fee_apr, I80F48::from_num(0.02));
+        // lending_rate = borrowing_rate * utilization * (1 - total_fee)
+        assert_eq!(lending_rate, I80F48::from_num(0.1 * 0.8 * 0.95));
+    }
+
+    fn test_bank(interest_rate_config: InterestRateConfig) -> Bank {
+        let config = BankConfig {
+            interest_rate_config,
+            ..Default::default()
+        };
+
+        Bank::new(config, Pubkey::default(), Pubkey::default(), Pubkey::default(), Pubkey::default())
+    }
+
+    #[test]
+    fn accrue_interest_is_noop_within_same_timestamp() {
+        let mut bank = test_bank(ir_config(0.8, 0.1, 0.2, 1.0, 0.0, 0.0));
+        bank.last_update = 100;
+
+        bank.accrue_interest(100).unwrap();
+
+        assert_eq!(bank.deposit_share_value, I80F48::ONE);
+        assert_eq!(bank.liability_share_value, I80F48::ONE);
+        assert_eq!(bank.last_update, 100);
+    }
+
+    #[test]
+    fn accrue_interest_compounds_liability_share_value_over_time() {
+        let mut bank = test_bank(ir_config(0.8, 0.1, 0.2, 1.0, 0.0, 0.0));
+        bank.total_deposit_shares = I80F48::from_num(100);
+        bank.total_borrow_shares = I80F48::from_num(80);
+        bank.last_update = 0;
+
+        bank.accrue_interest(SECONDS_PER_YEAR.to_num::<i64>()).unwrap();
+
+        // Utilization is exactly optimal (0.8), so borrowing_rate == optimal_borrow_rate == 0.2.
+        // Over exactly one year, liability_share_value compounds by (1 + 0.2).
+        assert_eq!(bank.liability_share_value, I80F48::from_num(1.2));
+        assert!(bank.deposit_share_value > I80F48::ONE);
+        assert_eq!(bank.last_update, SECONDS_PER_YEAR.to_num::<i64>());
+    }
+
+    #[test]
+    fn accrue_interest_credits_fee_shares_to_fee_and_insurance_vaults() {
+        let mut bank = test_bank(ir_config(0.75, 0.0, 0.25, 1.0, 0.0625, 0.0625));
+        bank.total_deposit_shares = I80F48::from_num(100);
+        bank.total_borrow_shares = I80F48::from_num(75);
+        bank.last_update = 0;
+
+        bank.accrue_interest(SECONDS_PER_YEAR.to_num::<i64>()).unwrap();
+
+        assert!(bank.fee_vault_deposit_shares > I80F48::ZERO);
+        assert!(bank.insurance_vault_deposit_shares > I80F48::ZERO);
+        assert_eq!(bank.fee_vault_deposit_shares, bank.insurance_vault_deposit_shares);
+    }
+
+    fn stable_price_model(max_relative_move_per_interval: f64, delay_interval_seconds: i64) -> StablePriceModel {
+        StablePriceModel {
+            max_relative_move_per_interval: I80F48::from_num(max_relative_move_per_interval).into(),
+            delay_interval_seconds,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn stable_price_model_bootstraps_from_zero() {
+        let mut model = stable_price_model(0.1, 3600);
+
+        model.update(I80F48::from_num(50), 1000).unwrap();
+
+        assert_eq!(I80F48::from(model.stable_price), I80F48::from_num(50));
+        assert_eq!(model.last_update_timestamp, 1000);
+    }
+
+    #[test]
+    fn stable_price_model_clamps_large_moves() {
+        let mut model = stable_price_model(0.25, 3600);
+        model.update(I80F48::from_num(100), 0).unwrap();
+
+        // One full interval later, the oracle price doubles; the move is capped at 25%.
+        model.update(I80F48::from_num(200), 3600).unwrap();
+
+        assert_eq!(I80F48::from(model.stable_price), I80F48::from_num(125));
+    }
+
+    #[test]
+    fn stable_price_model_follows_small_moves_uncapped() {
+        let mut model = stable_price_model(0.1, 3600);
+        model.update(I80F48::from_num(100), 0).unwrap();
+
+        model.update(I80F48::from_num(105), 3600).unwrap();
+
+        assert_eq!(I80F48::from(model.stable_price), I80F48::from_num(105));
+    }
+
+    fn test_bank_with_liquidation_params(close_factor: f64, bonus: f64, close_amount: f64) -> Bank {
+        let config = BankConfig {
+            liquidation_close_factor: I80F48::from_num(close_factor).into(),
+            liquidation_bonus: I80F48::from_num(bonus).into(),
+            liquidation_close_amount: I80F48::from_num(close_amount).into(),
+            ..Default::default()
+        };
+
+        Bank::new(config, Pubkey::default(), Pubkey::default(), Pubkey::default(), Pubkey::default())
+    }
+
+    #[test]
+    fn calc_max_liquidatable_liability_value_applies_close_factor() {
+        let bank = test_bank_with_liquidation_params(0.5, 0.05, 10.0);
+
+        let max_repayable = bank
+            .calc_max_liquidatable_liability_value(I80F48::from_num(1000))
+            .unwrap();
+
+        assert_eq!(max_repayable, I80F48::from_num(500));
+    }
+
+    #[test]
+    fn calc_max_liquidatable_liability_value_allows_full_close_under_dust_threshold() {
+        let bank = test_bank_with_liquidation_params(0.5, 0.05, 10.0);
+
+        let max_repayable = bank
+            .calc_max_liquidatable_liability_value(I80F48::from_num(5))
+            .unwrap();
+
+        assert_eq!(max_repayable, I80F48::from_num(5));
+    }
+
+    #[test]
+    fn calc_liquidation_seized_deposit_shares_applies_bonus() {
+        let bank = test_bank_with_liquidation_params(0.5, 0.1, 10.0);
+
+        // deposit_share_value defaults to 1, so shares == value.
+        let seized_shares = bank
+            .calc_liquidation_seized_deposit_shares(I80F48::from_num(100))
+            .unwrap();
+
+        assert_eq!(seized_shares, I80F48::from_num(110));
+    }
+
+    fn test_bank_with_borrow_limit(borrow_limit: u64) -> Bank {
+        let config = BankConfig {
+            borrow_limit,
+            ..Default::default()
+        };
+
+        Bank::new(config, Pubkey::default(), Pubkey::default(), Pubkey::default(), Pubkey::default())
+    }
+
+    #[test]
+    fn change_liability_shares_rejects_borrow_past_limit() {
+        let mut bank = test_bank_with_borrow_limit(100);
+
+        assert!(bank.change_liability_shares(I80F48::from_num(101)).is_err());
+    }
+
+    #[test]
+    fn change_liability_shares_allows_borrow_under_limit() {
+        let mut bank = test_bank_with_borrow_limit(100);
+
+        assert!(bank.change_liability_shares(I80F48::from_num(50)).is_ok());
+    }
+
+    fn test_bank_config_with_ramp(
+        start_weight: f64,
+        target_weight: f64,
+        ramp_start_ts: i64,
+        ramp_end_ts: i64,
+    ) -> BankConfig {
+        BankConfig {
+            liability_weight_maint: I80F48::from_num(start_weight).into(),
+            maint_weight_start: I80F48::from_num(start_weight).into(),
+            maint_weight_target: I80F48::from_num(target_weight).into(),
+            ramp_start_ts,
+            ramp_end_ts,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn get_weights_before_ramp_start_uses_start_weight() {
+        let config = test_bank_config_with_ramp(1.0, 1.5, 1000, 2000);
+
+        let (_, liability_weight) = config.get_weights(WeightType::Maintenance, 500).unwrap();
+
+        assert_eq!(liability_weight, I80F48::from_num(1.0));
+    }
+
+    #[test]
+    fn get_weights_mid_ramp_interpolates() {
+        let config = test_bank_config_with_ramp(1.0, 1.5, 1000, 2000);
+
+        let (_, liability_weight) = config.get_weights(WeightType::Maintenance, 1500).unwrap();
+
+        assert_eq!(liability_weight, I80F48::from_num(1.25));
+    }
+
+    #[test]
+    fn get_weights_after_ramp_end_uses_target_weight() {
+        let config = test_bank_config_with_ramp(1.0, 1.5, 1000, 2000);
+
+        let (_, liability_weight) = config.get_weights(WeightType::Maintenance, 3000).unwrap();
+
+        assert_eq!(liability_weight, I80F48::from_num(1.5));
+    }
+
+    #[test]
+    fn get_weights_without_ramp_uses_static_weight() {
+        let config = BankConfig {
+            liability_weight_maint: I80F48::from_num(1.2).into(),
+            ..Default::default()
+        };
+
+        let (_, liability_weight) = config.get_weights(WeightType::Maintenance, 12345).unwrap();
+
+        assert_eq!(liability_weight, I80F48::from_num(1.2));
+    }
+
+    fn test_bank_config_with_oracle(conf_filter: f64, max_staleness_slots: u64) -> BankConfig {
+        BankConfig {
+            oracle_config: OracleConfig {
+                conf_filter: I80F48::from_num(conf_filter).into(),
+                max_staleness_slots,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_and_scale_price_rejects_stale_slot() {
+        let config = test_bank_config_with_oracle(0.1, 10);
+
+        // Published at slot 100, now slot 111: 11 slots stale, past the 10-slot window.
+        let result = config.validate_and_scale_price(100_00, 1_00, -2, 100, 111);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn validate_and_scale_price_accepts_price_within_staleness_window() {
+        let config = test_bank_config_with_oracle(0.1, 10);
+
+        let price = config
+            .validate_and_scale_price(100_00, 1_00, -2, 100, 110)
+            .unwrap();
+
+        assert_eq!(price, I80F48::from_num(100));
+    }
+
+    #[test]
+    fn validate_and_scale_price_rejects_wide_confidence_interval() {
+        let config = test_bank_config_with_oracle(0.01, 10);
+
+        // conf is 5% of price, wider than the 1% conf_filter allows.
+        let result = config.validate_and_scale_price(100_00, 5_00, -2, 100, 100);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn validate_and_scale_price_accepts_tight_confidence_interval() {
+        let config = test_bank_config_with_oracle(0.1, 10);
+
+        // conf is 1% of price, within the 10% conf_filter.
+        let price = config
+            .validate_and_scale_price(100_00, 1_00, -2, 100, 100)
+            .unwrap();
+
+        assert_eq!(price, I80F48::from_num(100));
+    }
+
+    #[test]
+    fn validate_and_scale_price_scales_positive_expo() {
+        let config = test_bank_config_with_oracle(0.5, 10);
+
+        let price = config.validate_and_scale_price(5, 0, 2, 100, 100).unwrap();
+
+        assert_eq!(price, I80F48::from_num(500));
+    }
 }